@@ -4,14 +4,23 @@
 use std::result::Result;
 
 use std::sync::Arc;
+use std::time::Duration;
 
+use chrono::{Datelike, Utc};
+use futures::stream::{self, StreamExt};
 use prometheus::Encoder;
+use tokio::time::timeout;
 
 use super::kasa;
 
-/// Returns a future that implements a response for an exporter request.
+/// Maximum time to wait for a single device's emeter response before giving up on it.
+const EMETER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Returns a future that implements a response for an exporter request. `concurrency` caps how
+/// many devices are queried for emeter data at the same time.
 pub async fn serve<T>(
     client: Arc<kasa::Client<T>>,
+    concurrency: usize,
 ) -> Result<hyper::Response<hyper::Body>, hyper::Error>
 where
     T: hyper::client::connect::Connect + std::clone::Clone + std::marker::Send + Sync + 'static,
@@ -28,21 +37,123 @@ where
         }
     };
 
-    let emeters: Vec<(kasa::DeviceListEntry, kasa::EmeterResult)> = match devices.result {
+    let today = Utc::now();
+
+    let emeters: Vec<(
+        kasa::DeviceListEntry,
+        kasa::EmeterResult,
+        Option<kasa::SysinfoResult>,
+        Option<kasa::EmeterGetDaystatResult>,
+        Option<kasa::EmeterGetMonthstatResult>,
+    )> = match devices.result {
         Some(devices) => {
-            let mut results = Vec::new();
-            for device in devices.device_list {
-                match client.emeter(&device.device_id).await {
-                    Ok(emeter) => results.push((device, emeter)),
-                    Err(e) => eprintln!(
-                        "error reading device {} ({}): {}",
-                        device.alias,
-                        device.device_id,
-                        e.to_string()
-                    ),
-                };
-            }
-            results
+            stream::iter(devices.device_list)
+                .map(|device| {
+                    let client = client.clone();
+                    async move {
+                        let result =
+                            match timeout(EMETER_TIMEOUT, client.emeter(&device.device_id)).await {
+                                Ok(result) => result,
+                                Err(_) => {
+                                    eprintln!(
+                                        "timed out reading device {} ({})",
+                                        device.alias, device.device_id
+                                    );
+                                    return None;
+                                }
+                            };
+
+                        let emeter = match result {
+                            Ok(emeter) => emeter,
+                            Err(e) => {
+                                eprintln!(
+                                    "error reading device {} ({}): {}",
+                                    device.alias,
+                                    device.device_id,
+                                    e.to_string()
+                                );
+                                return None;
+                            }
+                        };
+
+                        let sysinfo =
+                            match timeout(EMETER_TIMEOUT, client.sysinfo(&device.device_id)).await {
+                                Ok(Ok(sysinfo)) => Some(sysinfo),
+                                Ok(Err(e)) => {
+                                    eprintln!(
+                                        "error reading sysinfo for device {} ({}): {}",
+                                        device.alias,
+                                        device.device_id,
+                                        e.to_string()
+                                    );
+                                    None
+                                }
+                                Err(_) => {
+                                    eprintln!(
+                                        "timed out reading sysinfo for device {} ({})",
+                                        device.alias, device.device_id
+                                    );
+                                    None
+                                }
+                            };
+
+                        let daystat = match timeout(
+                            EMETER_TIMEOUT,
+                            client.emeter_daystat(&device.device_id, today.year(), today.month()),
+                        )
+                        .await
+                        {
+                            Ok(Ok(daystat)) => Some(daystat),
+                            Ok(Err(e)) => {
+                                eprintln!(
+                                    "error reading daystat for device {} ({}): {}",
+                                    device.alias,
+                                    device.device_id,
+                                    e.to_string()
+                                );
+                                None
+                            }
+                            Err(_) => {
+                                eprintln!(
+                                    "timed out reading daystat for device {} ({})",
+                                    device.alias, device.device_id
+                                );
+                                None
+                            }
+                        };
+
+                        let monthstat = match timeout(
+                            EMETER_TIMEOUT,
+                            client.emeter_monthstat(&device.device_id, today.year()),
+                        )
+                        .await
+                        {
+                            Ok(Ok(monthstat)) => Some(monthstat),
+                            Ok(Err(e)) => {
+                                eprintln!(
+                                    "error reading monthstat for device {} ({}): {}",
+                                    device.alias,
+                                    device.device_id,
+                                    e.to_string()
+                                );
+                                None
+                            }
+                            Err(_) => {
+                                eprintln!(
+                                    "timed out reading monthstat for device {} ({})",
+                                    device.alias, device.device_id
+                                );
+                                None
+                            }
+                        };
+
+                        Some((device, emeter, sysinfo, daystat, monthstat))
+                    }
+                })
+                .buffer_unordered(concurrency)
+                .filter_map(|result| async { result })
+                .collect()
+                .await
         }
         None => vec![],
     };
@@ -89,7 +200,15 @@ macro_rules! fill_metric {
 }
 
 /// Creates a throw away registry to populate data for a request.
-fn registry(emeters: Vec<(kasa::DeviceListEntry, kasa::EmeterResult)>) -> prometheus::Registry {
+fn registry(
+    emeters: Vec<(
+        kasa::DeviceListEntry,
+        kasa::EmeterResult,
+        Option<kasa::SysinfoResult>,
+        Option<kasa::EmeterGetDaystatResult>,
+        Option<kasa::EmeterGetMonthstatResult>,
+    )>,
+) -> prometheus::Registry {
     let voltage = gauge_vec(
         "device_electric_potential_volts",
         "Voltage reading from device",
@@ -110,32 +229,101 @@ fn registry(emeters: Vec<(kasa::DeviceListEntry, kasa::EmeterResult)>) -> promet
         "Total energy consumed",
         &["device_alias", &"device_id"],
     );
+    let relay_state = gauge_vec(
+        "device_relay_state",
+        "Whether the device relay is on (1) or off (0)",
+        &["device_alias", &"device_id"],
+    );
+    let signal_strength = gauge_vec(
+        "device_signal_strength_dbm",
+        "Wi-Fi signal strength of the device",
+        &["device_alias", &"device_id"],
+    );
+    let uptime = gauge_vec(
+        "device_uptime_seconds_total",
+        "Time since the device was last powered on",
+        &["device_alias", &"device_id"],
+    );
+    let daily_energy = gauge_vec(
+        "device_electric_energy_daily_joules",
+        "Energy consumed on a given day of the current month",
+        &["device_alias", "device_id", "day"],
+    );
+    let monthly_energy = gauge_vec(
+        "device_electric_energy_monthly_joules",
+        "Energy consumed in a given month of the current year",
+        &["device_alias", "device_id", "month"],
+    );
 
     let registry = prometheus::Registry::new();
 
-    let collectors = vec![&voltage, &current, &power, &energy];
+    let collectors = vec![
+        &voltage,
+        &current,
+        &power,
+        &energy,
+        &relay_state,
+        &signal_strength,
+        &uptime,
+    ];
 
     for metric in collectors {
         registry.register(Box::new(metric.clone())).unwrap();
     }
 
-    for (device, emeter) in emeters {
-        let realtime = match emeter.get_realtime {
-            Some(realtime) => realtime,
-            None => continue,
-        };
+    registry.register(Box::new(daily_energy.clone())).unwrap();
+    registry.register(Box::new(monthly_energy.clone())).unwrap();
 
+    for (device, emeter, sysinfo, daystat, monthstat) in emeters {
         let labels = &prometheus::labels! {
                 "device_alias" => device.alias.as_str(),
                 "device_id"    => device.device_id.as_str(),
         };
 
-        fill_metric! { labels = labels,
-            voltage => realtime.voltage,
-            current => realtime.current,
-            power   => realtime.power,
-            energy  => realtime.total.map(|kwh| kwh * 3600.0 * 1000.0),
-        };
+        if let Some(realtime) = emeter.get_realtime {
+            fill_metric! { labels = labels,
+                voltage => realtime.voltage,
+                current => realtime.current,
+                power   => realtime.power,
+                energy  => realtime.total.map(|kwh| kwh * 3600.0 * 1000.0),
+            };
+        }
+
+        if let Some(sysinfo) = sysinfo {
+            fill_metric! { labels = labels,
+                relay_state      => sysinfo.relay_state.map(|state| state as f64),
+                signal_strength  => sysinfo.rssi.map(|rssi| rssi as f64),
+                uptime           => sysinfo.on_time.map(|on_time| on_time as f64),
+            };
+        }
+
+        if let Some(daystat) = daystat {
+            for day in daystat.day_list {
+                let day_string = day.day.to_string();
+
+                let labels = prometheus::labels! {
+                    "device_alias" => device.alias.as_str(),
+                    "device_id"    => device.device_id.as_str(),
+                    "day"          => day_string.as_str(),
+                };
+
+                daily_energy.with(&labels).set(day.energy_wh * 3600.0);
+            }
+        }
+
+        if let Some(monthstat) = monthstat {
+            for month in monthstat.month_list {
+                let month_string = month.month.to_string();
+
+                let labels = prometheus::labels! {
+                    "device_alias" => device.alias.as_str(),
+                    "device_id"    => device.device_id.as_str(),
+                    "month"        => month_string.as_str(),
+                };
+
+                monthly_energy.with(&labels).set(month.energy_wh * 3600.0);
+            }
+        }
     }
 
     registry
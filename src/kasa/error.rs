@@ -15,4 +15,8 @@ pub enum KasaError {
     EmptyPassthroughResponse {},
     #[error("Empty emeter response")]
     EmptyEmeterResponse {},
+    #[error("Empty sysinfo response")]
+    EmptySysinfoResponse {},
+    #[error("Device returned an error for a control command: code={code}")]
+    ControlError { code: i32 },
 }
@@ -0,0 +1,104 @@
+//! Direct LAN transport that speaks the Kasa local protocol to devices on port 9999,
+//! without going through TP-Link's cloud.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use anyhow::Result;
+use serde_derive::Deserialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::time::timeout;
+use tplink_shome_protocol::{decrypt, encrypt};
+
+use crate::kasa::error::KasaError;
+
+const BROADCAST_BIND_ADDR: &str = "0.0.0.0:0";
+const BROADCAST_SEND_ADDR: &str = "255.255.255.255:9999";
+const BROADCAST_RESPONSE_BUFFER_SIZE: usize = 4096;
+const BROADCAST_WAIT_TIME: Duration = Duration::from_millis(500);
+
+const DISCOVER_REQUEST: &str = r#"{"system":{"get_sysinfo":{}}}"#;
+
+/// Sends a request directly to a device on the LAN and returns the decoded response.
+pub(crate) async fn query<R>(addr: SocketAddr, request_data: &str) -> Result<R>
+where
+    R: serde::de::DeserializeOwned + std::fmt::Debug,
+{
+    let mut stream = TcpStream::connect(addr).await?;
+
+    let buf = encrypt(request_data.as_bytes());
+    stream.write_all(&(buf.len() as u32).to_be_bytes()).await?;
+    stream.write_all(&buf).await?;
+
+    let mut len_buf = [0; 4];
+    stream.read_exact(&mut len_buf).await?;
+
+    let mut buf = vec![0; u32::from_be_bytes(len_buf) as usize];
+    stream.read_exact(&mut buf).await?;
+
+    let decrypted = decrypt(&buf);
+
+    serde_json::from_slice(&decrypted).map_err(|e| {
+        KasaError::Serialization {
+            source: e.into(),
+            debug: String::from_utf8_lossy(&decrypted).to_string(),
+        }
+        .into()
+    })
+}
+
+/// Queries a single device's identity directly via `system.get_sysinfo`.
+pub(crate) async fn get_sysinfo(addr: SocketAddr) -> Result<Sysinfo> {
+    let response: DiscoverResponse = query(addr, DISCOVER_REQUEST).await?;
+    Ok(response.system.get_sysinfo)
+}
+
+/// Broadcasts a `system.get_sysinfo` request and collects replies from devices on the LAN.
+pub(crate) async fn discover() -> Result<Vec<(SocketAddr, Sysinfo)>> {
+    let socket = UdpSocket::bind(BROADCAST_BIND_ADDR).await?;
+    socket.set_broadcast(true)?;
+
+    let buf = encrypt(DISCOVER_REQUEST.as_bytes());
+    socket.send_to(&buf, BROADCAST_SEND_ADDR).await?;
+
+    let mut buf = [0u8; BROADCAST_RESPONSE_BUFFER_SIZE];
+    let mut devices = Vec::new();
+
+    while let Ok(Ok((n, addr))) = timeout(BROADCAST_WAIT_TIME, socket.recv_from(&mut buf)).await {
+        let response: DiscoverResponse = match serde_json::from_slice(&decrypt(&buf[0..n])) {
+            Ok(response) => response,
+            Err(_) => continue,
+        };
+
+        devices.push((addr, response.system.get_sysinfo));
+    }
+
+    Ok(devices)
+}
+
+#[derive(Debug, Deserialize)]
+struct DiscoverResponse {
+    system: DiscoverSystemResponse,
+}
+
+#[derive(Debug, Deserialize)]
+struct DiscoverSystemResponse {
+    get_sysinfo: Sysinfo,
+}
+
+/// Device identity reported by `system.get_sysinfo` over the LAN protocol.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Sysinfo {
+    pub(crate) alias: String,
+    pub(crate) model: String,
+
+    #[serde(rename = "deviceId")]
+    pub(crate) device_id: String,
+
+    #[serde(rename = "hw_ver")]
+    pub(crate) hardware_version: String,
+
+    #[serde(rename = "sw_ver")]
+    pub(crate) firmware_version: String,
+}
@@ -2,9 +2,13 @@
 //! A library for interacting with [TP-Link Kasa](https://www.kasasmart.com/) API
 
 use std::fmt;
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync;
 
 pub mod error;
+mod local;
+mod token_store;
 
 use crate::kasa::error::KasaError;
 
@@ -12,45 +16,124 @@ use anyhow::Result;
 
 const ENDPOINT: &str = "https://wap.tplinkcloud.com/";
 
-/// A client for interacting with API
+/// A client for interacting with API, either via TP-Link's cloud or directly over the LAN.
 pub struct Client<T> {
+    transport: Transport<T>,
+}
+
+enum Transport<T> {
+    Cloud(CloudTransport<T>),
+    Local(SocketAddr),
+}
+
+struct CloudTransport<T> {
     client: hyper::Client<T>,
     app: String,
     username: String,
     password: String,
     token: sync::Mutex<String>,
+    base_url: sync::Mutex<String>,
+    refresh_token: sync::Mutex<String>,
+    token_store: Option<PathBuf>,
 }
 
 impl<T> Client<T>
 where
     T: hyper::client::connect::Connect + std::clone::Clone + std::marker::Send + Sync + 'static,
 {
-    /// Creates a new client with http client, credentials, and an app name (arbitrary string).
+    /// Creates a new client that talks to TP-Link's cloud with http client, credentials, and an
+    /// app name (arbitrary string). When `token_store` is given, a previously persisted refresh
+    /// token is loaded from that path and used instead of the password, and the refresh token is
+    /// persisted back to it after every renewal.
     pub async fn new(
         client: hyper::Client<T>,
         app: String,
         username: String,
         password: String,
+        token_store: Option<PathBuf>,
     ) -> Result<Client<T>> {
-        let token = Self::auth(&client, app.clone(), username.clone(), password.clone()).await?;
+        let stored = token_store.as_deref().and_then(token_store::load);
+
+        let (token, base_url, refresh_token) = match stored {
+            Some(stored) => {
+                match Self::refresh(&client, &stored.base_url, app.clone(), stored.refresh_token)
+                    .await
+                {
+                    Ok(result) => result,
+                    Err(_) => {
+                        Self::auth(&client, ENDPOINT, app.clone(), username.clone(), password.clone())
+                            .await?
+                    }
+                }
+            }
+            None => {
+                Self::auth(&client, ENDPOINT, app.clone(), username.clone(), password.clone())
+                    .await?
+            }
+        };
+
+        if let Some(path) = &token_store {
+            Self::persist_token(path, &refresh_token, &base_url);
+        }
 
         Ok(Self {
-            client,
-            app,
-            username,
-            password,
-            token: sync::Mutex::new(token),
+            transport: Transport::Cloud(CloudTransport {
+                client,
+                app,
+                username,
+                password,
+                token: sync::Mutex::new(token),
+                base_url: sync::Mutex::new(base_url),
+                refresh_token: sync::Mutex::new(refresh_token),
+                token_store,
+            }),
         })
     }
 
+    /// Persists the refresh token, logging (rather than failing the caller) if the write fails.
+    fn persist_token(path: &PathBuf, refresh_token: &str, base_url: &str) {
+        let stored = token_store::StoredToken {
+            refresh_token: refresh_token.to_string(),
+            base_url: base_url.to_string(),
+        };
+
+        if let Err(e) = token_store::save(path, &stored) {
+            eprintln!("error persisting kasa token store at {:?}: {}", path, e);
+        }
+    }
+
+    /// Creates a new client that talks directly to a device on the LAN, bypassing the cloud
+    /// entirely. No credentials or login round-trip are required.
+    pub fn new_local(addr: SocketAddr) -> Client<T> {
+        Self {
+            transport: Transport::Local(addr),
+        }
+    }
+
+    /// Discovers devices on the local network by broadcasting to port 9999 and returns the
+    /// address of each device that responded, ready to be used with [`Client::new_local`].
+    pub async fn discover_local() -> Result<Vec<SocketAddr>> {
+        Ok(local::discover()
+            .await?
+            .into_iter()
+            .map(|(addr, _)| addr)
+            .collect())
+    }
+
+    /// Authenticates with the given base URL and returns the session token, the base URL
+    /// subsequent requests should use (the region-specific URL from the login response, if any,
+    /// falling back to the URL that was used to authenticate), and a refresh token that can be
+    /// used to renew the session without the password.
     async fn auth(
         client: &hyper::Client<T>,
+        base_url: &str,
         app: String,
         username: String,
         password: String,
-    ) -> Result<String> {
+    ) -> Result<(String, String, String)> {
         let auth_response: Response<AuthResult> = Self::query(
             client,
+            base_url,
             None,
             &Request {
                 method: "login".to_string(),
@@ -60,7 +143,41 @@ where
         .await?;
 
         if let Some(result) = auth_response.result {
-            Ok(result.token)
+            let base_url = result.regional_url.unwrap_or_else(|| base_url.to_string());
+            let refresh_token = result.refresh_token.unwrap_or_default();
+            Ok((result.token, base_url, refresh_token))
+        } else {
+            Err(KasaError::EmptyAuthResponse {
+                code: auth_response.error_code,
+                message: auth_response.message.unwrap_or_else(|| "".to_string()),
+            }
+            .into())
+        }
+    }
+
+    /// Exchanges a previously issued refresh token for a new access token, without resending the
+    /// account password.
+    async fn refresh(
+        client: &hyper::Client<T>,
+        base_url: &str,
+        app: String,
+        refresh_token: String,
+    ) -> Result<(String, String, String)> {
+        let auth_response: Response<AuthResult> = Self::query(
+            client,
+            base_url,
+            None,
+            &Request {
+                method: "refreshToken".to_string(),
+                params: RefreshTokenParams::new(app, refresh_token.clone()),
+            },
+        )
+        .await?;
+
+        if let Some(result) = auth_response.result {
+            let base_url = result.regional_url.unwrap_or_else(|| base_url.to_string());
+            let refresh_token = result.refresh_token.unwrap_or(refresh_token);
+            Ok((result.token, base_url, refresh_token))
         } else {
             Err(KasaError::EmptyAuthResponse {
                 code: auth_response.error_code,
@@ -73,6 +190,7 @@ where
     /// Send a request to API with an optional token.
     async fn query<Q, R>(
         client: &hyper::Client<T>,
+        base_url: &str,
         token: Option<&String>,
         request: &Request<Q>,
     ) -> Result<Response<R>>
@@ -88,7 +206,7 @@ where
 
         let mut http_request = hyper::Request::new(hyper::Body::from(request_body));
 
-        let mut uri = ENDPOINT.to_string();
+        let mut uri = base_url.to_string();
         if let Some(value) = token {
             uri = uri + &"?token=".to_string() + value
         }
@@ -129,34 +247,59 @@ where
     }
 
     /// Sends an authenticated request with a token provided by auth request.
-    async fn token_query<Q, R>(&self, req: &Request<Q>) -> Result<Response<R>>
+    async fn token_query<Q, R>(cloud: &CloudTransport<T>, req: &Request<Q>) -> Result<Response<R>>
     where
         Q: serde::ser::Serialize + std::fmt::Debug,
         R: serde::de::DeserializeOwned + std::fmt::Debug,
     {
-        let mut token = { self.token.lock().unwrap().clone() };
+        let mut token = { cloud.token.lock().unwrap().clone() };
+        let mut base_url = { cloud.base_url.lock().unwrap().clone() };
 
-        let result = Self::query::<Q, R>(&self.client, Some(&token), req).await?;
+        let result = Self::query::<Q, R>(&cloud.client, &base_url, Some(&token), req).await?;
 
         if result.error_code == -20675 || result.error_code == -20651 {
-            token = Self::auth(
-                &self.client,
-                self.app.clone(),
-                self.username.clone(),
-                self.password.clone(),
-            )
-            .await?;
-
-            let mut guarded_token = self.token.lock().unwrap();
-            *guarded_token = token.clone();
+            let refresh_token = { cloud.refresh_token.lock().unwrap().clone() };
+
+            let refreshed = if refresh_token.is_empty() {
+                None
+            } else {
+                Self::refresh(&cloud.client, &base_url, cloud.app.clone(), refresh_token)
+                    .await
+                    .ok()
+            };
+
+            let (new_token, new_base_url, new_refresh_token) = match refreshed {
+                Some(refreshed) => refreshed,
+                None => {
+                    Self::auth(
+                        &cloud.client,
+                        &base_url,
+                        cloud.app.clone(),
+                        cloud.username.clone(),
+                        cloud.password.clone(),
+                    )
+                    .await?
+                }
+            };
+
+            token = new_token;
+            base_url = new_base_url;
+
+            *cloud.token.lock().unwrap() = token.clone();
+            *cloud.base_url.lock().unwrap() = base_url.clone();
+            *cloud.refresh_token.lock().unwrap() = new_refresh_token.clone();
+
+            if let Some(path) = &cloud.token_store {
+                Self::persist_token(path, &new_refresh_token, &base_url);
+            }
         }
 
-        Self::query::<Q, R>(&self.client, Some(&token), req).await
+        Self::query::<Q, R>(&cloud.client, &base_url, Some(&token), req).await
     }
 
     /// Sends a request directly to device via API.
     async fn passthrough_query<R>(
-        &self,
+        cloud: &CloudTransport<T>,
         device_id: &str,
         req: &PassthroughParamsData,
     ) -> Result<Response<R>>
@@ -166,40 +309,194 @@ where
         let params = PassthroughParams::new(device_id.to_owned(), req)
             .map_err(|e| KasaError::PassthroughParams { source: e.into() })?;
 
-        self.token_query(&Request {
-            method: "passthrough".to_string(),
-            params,
-        })
+        Self::token_query(
+            cloud,
+            &Request {
+                method: "passthrough".to_string(),
+                params,
+            },
+        )
         .await
     }
 
-    /// Returns list of devices available to the client.
+    /// Returns list of devices available to the client. For a client connected directly to a
+    /// device over the LAN, this returns a single-entry list describing that device.
     pub async fn get_device_list(&self) -> Result<Response<DeviceListResult>> {
-        self.token_query(&Request {
-            method: "getDeviceList".to_string(),
-            params: DeviceListParams::new(),
-        })
-        .await
+        match &self.transport {
+            Transport::Cloud(cloud) => {
+                Self::token_query(
+                    cloud,
+                    &Request {
+                        method: "getDeviceList".to_string(),
+                        params: DeviceListParams::new(),
+                    },
+                )
+                .await
+            }
+            Transport::Local(addr) => {
+                let sysinfo = local::get_sysinfo(*addr).await?;
+
+                Ok(Response {
+                    error_code: 0,
+                    message: None,
+                    result: Some(DeviceListResult {
+                        device_list: vec![DeviceListEntry {
+                            alias: sysinfo.alias,
+                            status: 1,
+                            model: sysinfo.model,
+                            device_id: sysinfo.device_id,
+                            hardware_version: sysinfo.hardware_version,
+                            firmware_version: sysinfo.firmware_version,
+                        }],
+                    }),
+                })
+            }
+        }
+    }
+
+    /// Sends an emeter query built from the given parameters and returns the decoded result,
+    /// regardless of which field of the result (realtime, daystat, monthstat) the caller asked
+    /// for.
+    async fn emeter_result(&self, device_id: &str, params: EmeterParams) -> Result<EmeterResult> {
+        match &self.transport {
+            Transport::Cloud(cloud) => {
+                Self::passthrough_query::<PassthroughResult>(
+                    cloud,
+                    device_id,
+                    &PassthroughParamsData::new().add_emeter(params),
+                )
+                .await?
+                .result
+                .ok_or(KasaError::EmptyPassthroughResponse {})?
+                .unpack::<EmeterResultWrapper>()?
+                .emeter
+                .ok_or_else(|| KasaError::EmptyEmeterResponse {}.into())
+            }
+            Transport::Local(addr) => {
+                let data = PassthroughParamsData::new().add_emeter(params);
+                let request_data =
+                    serde_json::to_string(&data).map_err(|e| KasaError::Serialization {
+                        source: e.into(),
+                        debug: format!("{:?}", data),
+                    })?;
+
+                local::query::<EmeterResultWrapper>(*addr, &request_data)
+                    .await?
+                    .emeter
+                    .ok_or_else(|| KasaError::EmptyEmeterResponse {}.into())
+            }
+        }
     }
 
     /// Returns emeter measurements from a supplied device.
     pub async fn emeter(&self, device_id: &str) -> Result<EmeterResult> {
-        self.passthrough_query::<PassthroughResult>(
-            device_id,
-            &PassthroughParamsData::new().add_emeter(EmeterParams::new().add_realtime()),
-        )
-        .await?
-        .result
-        .ok_or(KasaError::EmptyPassthroughResponse {})?
-        .unpack::<EmeterResultWrapper>()?
-        .emeter
-        .ok_or_else(|| KasaError::EmptyEmeterResponse {}.into())
+        self.emeter_result(device_id, EmeterParams::new().add_realtime())
+            .await
+    }
+
+    /// Returns daily energy totals for the given month from a supplied device.
+    pub async fn emeter_daystat(
+        &self,
+        device_id: &str,
+        year: i32,
+        month: u32,
+    ) -> Result<EmeterGetDaystatResult> {
+        self.emeter_result(device_id, EmeterParams::new().add_daystat(year, month))
+            .await?
+            .get_daystat
+            .ok_or_else(|| KasaError::EmptyEmeterResponse {}.into())
+    }
+
+    /// Returns monthly energy totals for the given year from a supplied device.
+    pub async fn emeter_monthstat(
+        &self,
+        device_id: &str,
+        year: i32,
+    ) -> Result<EmeterGetMonthstatResult> {
+        self.emeter_result(device_id, EmeterParams::new().add_monthstat(year))
+            .await?
+            .get_monthstat
+            .ok_or_else(|| KasaError::EmptyEmeterResponse {}.into())
+    }
+
+    /// Sends a system query built from the given parameters and returns the decoded result,
+    /// regardless of which field of the result (sysinfo, a control ack, ...) the caller asked
+    /// for.
+    async fn system_result(&self, device_id: &str, params: SystemParams) -> Result<SystemResult> {
+        match &self.transport {
+            Transport::Cloud(cloud) => {
+                Self::passthrough_query::<PassthroughResult>(
+                    cloud,
+                    device_id,
+                    &PassthroughParamsData::new().add_system(params),
+                )
+                .await?
+                .result
+                .ok_or(KasaError::EmptyPassthroughResponse {})?
+                .unpack::<SysinfoResultWrapper>()?
+                .system
+                .ok_or_else(|| KasaError::EmptySysinfoResponse {}.into())
+            }
+            Transport::Local(addr) => {
+                let data = PassthroughParamsData::new().add_system(params);
+                let request_data =
+                    serde_json::to_string(&data).map_err(|e| KasaError::Serialization {
+                        source: e.into(),
+                        debug: format!("{:?}", data),
+                    })?;
+
+                local::query::<SysinfoResultWrapper>(*addr, &request_data)
+                    .await?
+                    .system
+                    .ok_or_else(|| KasaError::EmptySysinfoResponse {}.into())
+            }
+        }
+    }
+
+    /// Returns device state (relay, Wi-Fi signal, uptime) from a supplied device.
+    pub async fn sysinfo(&self, device_id: &str) -> Result<SysinfoResult> {
+        self.system_result(device_id, SystemParams::new().add_sysinfo())
+            .await?
+            .get_sysinfo
+            .ok_or_else(|| KasaError::EmptySysinfoResponse {}.into())
+    }
+
+    /// Turns a device's relay on or off.
+    pub async fn set_relay_state(&self, device_id: &str, on: bool) -> Result<()> {
+        self.system_result(device_id, SystemParams::new().add_set_relay_state(on))
+            .await?
+            .set_relay_state
+            .ok_or_else(|| KasaError::EmptySysinfoResponse {}.into())?
+            .check()
+    }
+
+    /// Turns a device's status LED on or off.
+    pub async fn set_led_off(&self, device_id: &str, off: bool) -> Result<()> {
+        self.system_result(device_id, SystemParams::new().add_set_led_off(off))
+            .await?
+            .set_led_off
+            .ok_or_else(|| KasaError::EmptySysinfoResponse {}.into())?
+            .check()
+    }
+
+    /// Reboots a device.
+    pub async fn reboot(&self, device_id: &str) -> Result<()> {
+        self.system_result(device_id, SystemParams::new().add_reboot())
+            .await?
+            .reboot
+            .ok_or_else(|| KasaError::EmptySysinfoResponse {}.into())?
+            .check()
     }
 }
 
 impl<T> fmt::Debug for Client<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Kasa {{ token: {} }}", self.token.lock().unwrap())
+        match &self.transport {
+            Transport::Cloud(cloud) => {
+                write!(f, "Kasa {{ token: {} }}", cloud.token.lock().unwrap())
+            }
+            Transport::Local(addr) => write!(f, "Kasa {{ local: {} }}", addr),
+        }
     }
 }
 
@@ -238,6 +535,30 @@ impl AuthParams {
     }
 }
 
+/// Parameters for exchanging a refresh token for a new access token.
+#[derive(Debug, serde_derive::Serialize)]
+struct RefreshTokenParams {
+    #[serde(rename = "appType")]
+    app_type: String,
+
+    #[serde(rename = "refreshToken")]
+    refresh_token: String,
+
+    #[serde(rename = "terminalUUID")]
+    terminal_uuid: String,
+}
+
+impl RefreshTokenParams {
+    /// Creates refresh-token grant parameters.
+    fn new(app_type: String, refresh_token: String) -> Self {
+        Self {
+            app_type,
+            refresh_token,
+            terminal_uuid: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+}
+
 /// A generic response from Kasa API.
 #[derive(Debug, serde_derive::Deserialize)]
 pub struct Response<T> {
@@ -256,6 +577,16 @@ struct AuthResult {
     email: String,
 
     token: String,
+
+    /// Region-specific API base URL that subsequent requests should be sent to. Absent for
+    /// accounts that stay on the default endpoint.
+    #[serde(rename = "regionalUrl")]
+    regional_url: Option<String>,
+
+    /// Token that can be exchanged for a new access token without the password. Not always
+    /// re-issued on a refresh-grant response, in which case the previous one stays valid.
+    #[serde(rename = "refreshToken")]
+    refresh_token: Option<String>,
 }
 
 /// Parameters for device list request.
@@ -346,12 +677,18 @@ impl PassthroughResult {
 struct PassthroughParamsData {
     #[serde(skip_serializing_if = "Option::is_none")]
     emeter: Option<EmeterParams>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<SystemParams>,
 }
 
 impl PassthroughParamsData {
     /// Creates empty passthrough parameters.
     fn new() -> Self {
-        Self { emeter: None }
+        Self {
+            emeter: None,
+            system: None,
+        }
     }
 
     /// Adds query for emeter data.
@@ -359,6 +696,12 @@ impl PassthroughParamsData {
         self.emeter = Some(emeter);
         self
     }
+
+    /// Adds query for system data.
+    fn add_system(mut self, system: SystemParams) -> Self {
+        self.system = Some(system);
+        self
+    }
 }
 
 /// Parameters for emeter requests.
@@ -366,12 +709,22 @@ impl PassthroughParamsData {
 struct EmeterParams {
     #[serde(skip_serializing_if = "Option::is_none")]
     get_realtime: Option<EmeterGetRealtimeParams>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    get_daystat: Option<EmeterGetDaystatParams>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    get_monthstat: Option<EmeterGetMonthstatParams>,
 }
 
 impl EmeterParams {
     /// Creates empty emeter parameters.
     fn new() -> Self {
-        Self { get_realtime: None }
+        Self {
+            get_realtime: None,
+            get_daystat: None,
+            get_monthstat: None,
+        }
     }
 
     /// Adds query for realtime data.
@@ -379,12 +732,156 @@ impl EmeterParams {
         self.get_realtime = Some(EmeterGetRealtimeParams {});
         self
     }
+
+    /// Adds query for daily energy totals of a given month.
+    fn add_daystat(mut self, year: i32, month: u32) -> Self {
+        self.get_daystat = Some(EmeterGetDaystatParams { year, month });
+        self
+    }
+
+    /// Adds query for monthly energy totals of a given year.
+    fn add_monthstat(mut self, year: i32) -> Self {
+        self.get_monthstat = Some(EmeterGetMonthstatParams { year });
+        self
+    }
 }
 
 /// Parameters for realtime emeter data.
 #[derive(Debug, serde_derive::Serialize)]
 struct EmeterGetRealtimeParams {}
 
+/// Parameters for daily energy stat data.
+#[derive(Debug, serde_derive::Serialize)]
+struct EmeterGetDaystatParams {
+    year: i32,
+    month: u32,
+}
+
+/// Parameters for monthly energy stat data.
+#[derive(Debug, serde_derive::Serialize)]
+struct EmeterGetMonthstatParams {
+    year: i32,
+}
+
+/// Parameters for system requests.
+#[derive(Debug, serde_derive::Serialize)]
+struct SystemParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    get_sysinfo: Option<SystemGetSysinfoParams>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    set_relay_state: Option<SystemSetRelayStateParams>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    set_led_off: Option<SystemSetLedOffParams>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reboot: Option<SystemRebootParams>,
+}
+
+impl SystemParams {
+    /// Creates empty system parameters.
+    fn new() -> Self {
+        Self {
+            get_sysinfo: None,
+            set_relay_state: None,
+            set_led_off: None,
+            reboot: None,
+        }
+    }
+
+    /// Adds query for device info.
+    fn add_sysinfo(mut self) -> Self {
+        self.get_sysinfo = Some(SystemGetSysinfoParams {});
+        self
+    }
+
+    /// Adds a command to turn the relay on or off.
+    fn add_set_relay_state(mut self, on: bool) -> Self {
+        self.set_relay_state = Some(SystemSetRelayStateParams { state: on as i32 });
+        self
+    }
+
+    /// Adds a command to turn the status LED on or off.
+    fn add_set_led_off(mut self, off: bool) -> Self {
+        self.set_led_off = Some(SystemSetLedOffParams { off: off as i32 });
+        self
+    }
+
+    /// Adds a command to reboot the device.
+    fn add_reboot(mut self) -> Self {
+        self.reboot = Some(SystemRebootParams { delay: 1 });
+        self
+    }
+}
+
+/// Parameters for sysinfo data.
+#[derive(Debug, serde_derive::Serialize)]
+struct SystemGetSysinfoParams {}
+
+/// Parameters for a relay on/off command.
+#[derive(Debug, serde_derive::Serialize)]
+struct SystemSetRelayStateParams {
+    state: i32,
+}
+
+/// Parameters for a status LED on/off command.
+#[derive(Debug, serde_derive::Serialize)]
+struct SystemSetLedOffParams {
+    off: i32,
+}
+
+/// Parameters for a reboot command. `delay` is in seconds.
+#[derive(Debug, serde_derive::Serialize)]
+struct SystemRebootParams {
+    delay: i32,
+}
+
+/// A wrapper for system results.
+#[derive(Debug, serde_derive::Deserialize)]
+struct SysinfoResultWrapper {
+    system: Option<SystemResult>,
+}
+
+/// Results of a system request.
+#[derive(Debug, serde_derive::Deserialize)]
+pub struct SystemResult {
+    pub get_sysinfo: Option<SysinfoResult>,
+    pub set_relay_state: Option<Ack>,
+    pub set_led_off: Option<Ack>,
+    pub reboot: Option<Ack>,
+}
+
+/// Device state reported by `system.get_sysinfo`.
+#[derive(Debug, serde_derive::Deserialize)]
+pub struct SysinfoResult {
+    pub relay_state: Option<i32>,
+    pub rssi: Option<i32>,
+    pub on_time: Option<u64>,
+    pub led_off: Option<i32>,
+    pub updating: Option<i32>,
+}
+
+/// Acknowledgement returned by device-control commands.
+#[derive(Debug, serde_derive::Deserialize)]
+pub struct Ack {
+    pub err_code: i32,
+}
+
+impl Ack {
+    /// Turns a non-zero `err_code` into an error.
+    fn check(self) -> Result<()> {
+        if self.err_code == 0 {
+            Ok(())
+        } else {
+            Err(KasaError::ControlError {
+                code: self.err_code,
+            }
+            .into())
+        }
+    }
+}
+
 /// A wrapper for emeter results.
 #[derive(Debug, serde_derive::Deserialize)]
 struct EmeterResultWrapper {
@@ -395,6 +892,8 @@ struct EmeterResultWrapper {
 #[derive(Debug, serde_derive::Deserialize)]
 pub struct EmeterResult {
     pub get_realtime: Option<EmeterGetRealtimeResult>,
+    pub get_daystat: Option<EmeterGetDaystatResult>,
+    pub get_monthstat: Option<EmeterGetMonthstatResult>,
 }
 
 /// Realtime measurements from an emeter request.
@@ -406,3 +905,31 @@ pub struct EmeterGetRealtimeResult {
     pub power: Option<f64>,
     pub total: Option<f64>,
 }
+
+/// Daily energy totals for a month from an emeter request.
+#[derive(Debug, serde_derive::Deserialize)]
+pub struct EmeterGetDaystatResult {
+    #[serde(rename = "day_list")]
+    pub day_list: Vec<EmeterDayStat>,
+}
+
+/// A single day's energy total.
+#[derive(Debug, serde_derive::Deserialize)]
+pub struct EmeterDayStat {
+    pub day: u32,
+    pub energy_wh: f64,
+}
+
+/// Monthly energy totals for a year from an emeter request.
+#[derive(Debug, serde_derive::Deserialize)]
+pub struct EmeterGetMonthstatResult {
+    #[serde(rename = "month_list")]
+    pub month_list: Vec<EmeterMonthStat>,
+}
+
+/// A single month's energy total.
+#[derive(Debug, serde_derive::Deserialize)]
+pub struct EmeterMonthStat {
+    pub month: u32,
+    pub energy_wh: f64,
+}
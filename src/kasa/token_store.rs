@@ -0,0 +1,31 @@
+//! On-disk persistence for the cloud refresh token so the exporter can survive restarts and
+//! password rotations without replaying the account password.
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use anyhow::Result;
+use serde_derive::{Deserialize, Serialize};
+
+/// Refresh-token state persisted between process restarts.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct StoredToken {
+    pub(crate) refresh_token: String,
+    pub(crate) base_url: String,
+}
+
+/// Loads a previously persisted refresh token, if the file exists and is readable.
+pub(crate) fn load(path: &Path) -> Option<StoredToken> {
+    let data = fs::read(path).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+/// Persists the refresh token so it survives a restart. The file is written with `0o600`
+/// permissions since it contains credentials, unlike other persisted state.
+pub(crate) fn save(path: &Path, token: &StoredToken) -> Result<()> {
+    let data = serde_json::to_vec(token)?;
+    fs::write(path, data)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    Ok(())
+}
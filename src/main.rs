@@ -1,9 +1,11 @@
 use std::{
     collections::HashMap,
     io::{Error, ErrorKind, Result},
-    net::SocketAddr,
+    net::{SocketAddr, ToSocketAddrs},
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
     sync::{atomic::AtomicU64, Arc, Mutex},
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use axum::{
@@ -20,13 +22,13 @@ use prometheus_client::{
     metrics::{counter::Counter, family::Family, gauge::Gauge},
     registry::Registry,
 };
-use serde_derive::Deserialize;
+use serde_derive::{Deserialize, Serialize};
 use serde_json::from_slice;
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     net::UdpSocket,
 };
-use tokio::{net::TcpStream, time::timeout};
+use tokio::{net::TcpStream, sync::Semaphore, time::timeout};
 use tplink_shome_protocol::{decrypt, encrypt};
 
 const BROADCAST_BIND_ADDR: &str = "0.0.0.0:0";
@@ -42,6 +44,8 @@ const DEFAULT_PROMETHEUS_BIND_ADDR: &str = "[::1]:12345";
 
 const PROMETHEUS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
 
+const JSON_CONTENT_TYPE: &str = "application/json";
+
 const FORGET_TIMEOUT: Duration = Duration::from_secs(60 * 30);
 
 #[derive(Parser)]
@@ -50,11 +54,33 @@ struct Args {
     /// Address on which to expose metrics and web interface.
     #[arg(long = "web.listen-address", default_value = DEFAULT_PROMETHEUS_BIND_ADDR)]
     listen_address: String,
+
+    /// Path to persist discovered device endpoints to, so they survive a restart without
+    /// waiting for a fresh broadcast round. Entries older than `FORGET_TIMEOUT` are dropped
+    /// on load.
+    #[arg(long = "target.persist-file")]
+    persist_file: Option<String>,
+
+    /// Path to a newline-delimited list of `host:port` targets that are scraped on every
+    /// request in addition to whatever LAN broadcast discovers, for devices broadcast can't
+    /// reach (other subnets or VLANs).
+    #[arg(long = "target.file")]
+    target_file: Option<String>,
+
+    /// Maximum number of devices to check concurrently per scrape, so a large known-endpoint
+    /// set doesn't open hundreds of simultaneous TCP connections at once.
+    #[arg(long = "scrape.max-concurrency", default_value_t = 16)]
+    max_concurrency: usize,
 }
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
-    let args = Args::parse();
+    let mut args = Args::parse();
+
+    if args.max_concurrency == 0 {
+        eprintln!("--scrape.max-concurrency must be at least 1, using 1");
+        args.max_concurrency = 1;
+    }
 
     let addr = args
         .listen_address
@@ -63,9 +89,33 @@ async fn main() {
 
     eprintln!("listening on {}", args.listen_address);
 
+    let persist_file = args.persist_file.map(PathBuf::from);
+
+    let endpoints = persist_file
+        .as_deref()
+        .map(load_persisted_endpoints)
+        .unwrap_or_default();
+
+    let discovery: Vec<Box<dyn Discovery>> = match &args.target_file {
+        Some(path) => match StaticFileDiscovery::load(Path::new(path)) {
+            Ok(source) => vec![Box::new(source) as Box<dyn Discovery>],
+            Err(e) => {
+                eprintln!("error loading target file {path}: {e}");
+                vec![]
+            }
+        },
+        None => vec![],
+    };
+
     let app = Router::new()
         .route("/metrics", get(metrics))
-        .with_state(AppState::default());
+        .with_state(AppState {
+            endpoints: Arc::new(Mutex::new(endpoints)),
+            persist_file,
+            connections: Default::default(),
+            discovery: Arc::new(discovery),
+            scrape_semaphore: Arc::new(Semaphore::new(args.max_concurrency)),
+        });
 
     Server::bind(&addr)
         .serve(app.into_make_service())
@@ -73,12 +123,151 @@ async fn main() {
         .expect("error running server");
 }
 
-#[derive(Default, Clone)]
+#[derive(Clone)]
 struct AppState {
     endpoints: Arc<Mutex<HashMap<SocketAddr, Instant>>>,
+    persist_file: Option<PathBuf>,
+    connections: Arc<Mutex<HashMap<SocketAddr, TcpStream>>>,
+    discovery: Arc<Vec<Box<dyn Discovery>>>,
+    scrape_semaphore: Arc<Semaphore>,
+}
+
+/// A source of target addresses to scrape in addition to whatever LAN broadcast discovers.
+/// Targets it returns are checked on every scrape regardless of past failures, so implementors
+/// don't need to worry about `FORGET_TIMEOUT` bookkeeping themselves.
+trait Discovery: Send + Sync {
+    /// Returns the current set of addresses this source knows about.
+    fn targets(&self) -> Vec<SocketAddr>;
 }
 
-async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+/// Discovers targets from a fixed, newline-delimited list of `host:port` entries loaded once
+/// at startup.
+struct StaticFileDiscovery {
+    targets: Vec<SocketAddr>,
+}
+
+impl StaticFileDiscovery {
+    /// Loads the target list from `path`, resolving each `host:port` entry via DNS.
+    fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read_to_string(path)?;
+
+        let targets = data
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                line.to_socket_addrs()
+                    .map_err(|e| {
+                        Error::new(ErrorKind::InvalidData, format!("invalid target {line:?}: {e}"))
+                    })?
+                    .next()
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::InvalidData,
+                            format!("target {line:?} resolved to no addresses"),
+                        )
+                    })
+            })
+            .collect::<Result<Vec<SocketAddr>>>()?;
+
+        Ok(Self { targets })
+    }
+}
+
+impl Discovery for StaticFileDiscovery {
+    fn targets(&self) -> Vec<SocketAddr> {
+        self.targets.clone()
+    }
+}
+
+/// An endpoint's address and last-seen time as persisted to the beacon file.
+#[derive(Serialize, Deserialize)]
+struct PersistedEndpoint {
+    addr: SocketAddr,
+    last_seen: u64,
+}
+
+/// Loads previously persisted endpoints, dropping any entry not seen within `FORGET_TIMEOUT`.
+fn load_persisted_endpoints(path: &Path) -> HashMap<SocketAddr, Instant> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == ErrorKind::NotFound => return HashMap::default(),
+        Err(e) => {
+            eprintln!("error reading persisted endpoints at {}: {e}", path.display());
+            return HashMap::default();
+        }
+    };
+
+    let entries: Vec<PersistedEndpoint> = match serde_json::from_slice(&data) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("error parsing persisted endpoints at {}: {e}", path.display());
+            return HashMap::default();
+        }
+    };
+
+    let now_unix = unix_now();
+    let now = Instant::now();
+
+    entries
+        .into_iter()
+        .filter_map(|entry| {
+            let age = now_unix.saturating_sub(entry.last_seen);
+            if age > FORGET_TIMEOUT.as_secs() {
+                return None;
+            }
+
+            Some((
+                entry.addr,
+                now.checked_sub(Duration::from_secs(age)).unwrap_or(now),
+            ))
+        })
+        .collect()
+}
+
+/// Atomically persists the given endpoints to `path` as address and `last_seen` pairs.
+fn persist_endpoints(path: &Path, endpoints: &HashMap<SocketAddr, Instant>) {
+    let now_unix = unix_now();
+    let now = Instant::now();
+
+    let entries: Vec<PersistedEndpoint> = endpoints
+        .iter()
+        .map(|(addr, last_seen)| PersistedEndpoint {
+            addr: *addr,
+            last_seen: now_unix.saturating_sub(now.duration_since(*last_seen).as_secs()),
+        })
+        .collect();
+
+    let data = match serde_json::to_vec(&entries) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("error serializing persisted endpoints: {e}");
+            return;
+        }
+    };
+
+    let tmp_path = path.with_extension("tmp");
+
+    let result = std::fs::write(&tmp_path, data)
+        .and_then(|_| {
+            std::fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o644))
+        })
+        .and_then(|_| std::fs::rename(&tmp_path, path));
+
+    if let Err(e) = result {
+        eprintln!("error persisting endpoints to {}: {e}", path.display());
+    }
+}
+
+/// Returns the current time as a unix timestamp in seconds.
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+async fn metrics(State(state): State<AppState>, request_headers: HeaderMap) -> impl IntoResponse {
     let now = Instant::now();
 
     let mut responses = broadcast().await.unwrap_or_else(|e| {
@@ -96,15 +285,20 @@ async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
 
     let mut rechecks = vec![];
 
+    let connections = &state.connections;
+    let semaphore = &state.scrape_semaphore;
+
     for (endpoint, last_seen) in endpoints.iter() {
         if let Some(response) = responses.remove(endpoint) {
             combined.push(response);
         } else {
             rechecks.push(async move {
+                let _permit = semaphore.acquire().await.expect("semaphore closed");
+
                 (
                     endpoint,
                     last_seen,
-                    match timeout(RESPONSE_WAIT_TIME, check_one(endpoint)).await {
+                    match timeout(RESPONSE_WAIT_TIME, check_one(connections, endpoint)).await {
                         Ok(Ok(response)) => Some(response),
                         Ok(Err(e)) => {
                             eprintln!("error checking {endpoint}: {e}");
@@ -120,8 +314,40 @@ async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
         }
     }
 
+    let static_targets: Vec<SocketAddr> = state
+        .discovery
+        .iter()
+        .flat_map(|source| source.targets())
+        .collect();
+
+    let mut static_checks = vec![];
+
+    for target in &static_targets {
+        if responses.contains_key(target) || endpoints.contains_key(target) {
+            continue;
+        }
+
+        static_checks.push(async move {
+            let _permit = semaphore.acquire().await.expect("semaphore closed");
+
+            match timeout(RESPONSE_WAIT_TIME, check_one(connections, target)).await {
+                Ok(Ok(response)) => Some(response),
+                Ok(Err(e)) => {
+                    eprintln!("error checking static target {target}: {e}");
+                    None
+                }
+                Err(e) => {
+                    eprintln!("timed out checking static target {target}: {e}");
+                    None
+                }
+            }
+        });
+    }
+
     let rechecks = join_all(rechecks).await;
 
+    combined.extend(join_all(static_checks).await.into_iter().flatten());
+
     let mut remove = vec![];
 
     for (endpoint, last_seen, response) in rechecks {
@@ -151,16 +377,48 @@ async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
         combined.push(response);
     }
 
-    let registry = into_registry(combined);
+    if let Some(path) = &state.persist_file {
+        persist_endpoints(path, &endpoints);
+    }
+
+    drop(endpoints);
+
+    let wants_json = request_headers
+        .get(axum::http::header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains(JSON_CONTENT_TYPE));
+
+    let (content_type, buffer) = if wants_json {
+        let readings: Vec<DeviceReading> = combined
+            .into_iter()
+            .filter_map(|response| {
+                let realtime = response.emeter.get_realtime?;
+
+                Some(DeviceReading {
+                    device_alias: response.system.get_sysinfo.alias,
+                    device_id: response.system.get_sysinfo.device_id,
+                    voltage: realtime.voltage(),
+                    current: realtime.current(),
+                    power: realtime.power(),
+                    energy: realtime.energy(),
+                })
+            })
+            .collect();
 
-    let mut buffer = String::new();
-    encode(&mut buffer, &registry).expect("error encoding prometheus data");
+        let buffer = serde_json::to_string(&readings).expect("error encoding json data");
+
+        (JSON_CONTENT_TYPE, buffer)
+    } else {
+        let registry = into_registry(combined);
+
+        let mut buffer = String::new();
+        encode(&mut buffer, &registry).expect("error encoding prometheus data");
+
+        (PROMETHEUS_CONTENT_TYPE, buffer)
+    };
 
     let mut headers = HeaderMap::new();
-    headers.insert(
-        "content-type",
-        HeaderValue::from_static(PROMETHEUS_CONTENT_TYPE),
-    );
+    headers.insert("content-type", HeaderValue::from_static(content_type));
 
     (headers, buffer)
 }
@@ -187,21 +445,65 @@ async fn broadcast() -> Result<HashMap<SocketAddr, Response>> {
     Ok(responses)
 }
 
-async fn check_one(endpoint: &SocketAddr) -> Result<Response> {
-    let mut stream = TcpStream::connect(endpoint).await?;
+/// Checks a single device, reusing a pooled connection when one is available. A pooled
+/// connection that fails with a broken-pipe/reset/EOF error (Kasa devices silently close idle
+/// sockets) is discarded and the check is retried exactly once over a fresh connection. The
+/// stream is only returned to the pool after a fully successful request/response round trip, so
+/// a half-written frame is never left sitting in a pooled stream.
+async fn check_one(
+    connections: &Mutex<HashMap<SocketAddr, TcpStream>>,
+    endpoint: &SocketAddr,
+) -> Result<Response> {
+    let pooled = connections
+        .lock()
+        .expect("error locking connections")
+        .remove(endpoint);
+
+    let (response, stream) = match pooled {
+        Some(stream) => match query_stream(stream).await {
+            Ok(result) => result,
+            Err(e) if is_reconnectable(&e) => {
+                query_stream(TcpStream::connect(endpoint).await?).await?
+            }
+            Err(e) => return Err(e),
+        },
+        None => query_stream(TcpStream::connect(endpoint).await?).await?,
+    };
+
+    connections
+        .lock()
+        .expect("error locking connections")
+        .insert(*endpoint, stream);
+
+    Ok(response)
+}
 
+/// Sends the emeter/sysinfo request over an existing stream and reads the response, returning
+/// the stream for reuse on success.
+async fn query_stream(mut stream: TcpStream) -> Result<(Response, TcpStream)> {
     let buf = encrypt(REQUEST);
     stream.write_all(&(buf.len() as u32).to_be_bytes()).await?;
-
     stream.write_all(&buf).await?;
 
-    let mut buf = [0; 4];
-    stream.read_exact(&mut buf).await?;
+    let mut len_buf = [0; 4];
+    stream.read_exact(&mut len_buf).await?;
 
-    let mut buf: Vec<u8> = vec![0; u32::from_be_bytes(buf) as usize];
+    let mut buf: Vec<u8> = vec![0; u32::from_be_bytes(len_buf) as usize];
     stream.read_exact(&mut buf).await?;
 
-    from_slice(&decrypt(&buf)).map_err(|_| Error::from(ErrorKind::InvalidData))
+    let response =
+        from_slice(&decrypt(&buf)).map_err(|_| Error::from(ErrorKind::InvalidData))?;
+
+    Ok((response, stream))
+}
+
+/// Returns whether an I/O error means the pooled connection was closed by the peer and a fresh
+/// connection should be tried instead of surfacing the error immediately.
+fn is_reconnectable(e: &Error) -> bool {
+    matches!(
+        e.kind(),
+        ErrorKind::BrokenPipe | ErrorKind::ConnectionReset | ErrorKind::UnexpectedEof
+    )
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
@@ -329,3 +631,14 @@ impl GetRealtimeResponse {
             * 3600.0
     }
 }
+
+/// A single device's realtime reading, for the `application/json` response format.
+#[derive(Serialize)]
+struct DeviceReading {
+    device_alias: String,
+    device_id: String,
+    voltage: f64,
+    current: f64,
+    power: f64,
+    energy: f64,
+}